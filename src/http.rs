@@ -0,0 +1,492 @@
+//! HTTP primitives shared by the server binary and anything embedding this
+//! crate as a library: request/response types and the `Router` used to
+//! dispatch a parsed request to a handler.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::sync::Arc;
+
+/// The HTTP method of a request.
+///
+/// Methods outside the common set are preserved as `Other` and can still be
+/// matched by routes registered with [`Router::route`], but two different
+/// unrecognized methods are indistinguishable to the router.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Method {
+    Get,
+    Post,
+    Put,
+    Delete,
+    Patch,
+    Head,
+    Options,
+    Other,
+}
+
+impl Method {
+    fn parse(s: &str) -> Method {
+        match s {
+            "GET" => Method::Get,
+            "POST" => Method::Post,
+            "PUT" => Method::Put,
+            "DELETE" => Method::Delete,
+            "PATCH" => Method::Patch,
+            "HEAD" => Method::Head,
+            "OPTIONS" => Method::Options,
+            _ => Method::Other,
+        }
+    }
+}
+
+/// A parsed HTTP request.
+///
+/// `path` is the raw request-target (e.g. `/users/42`); `params` holds
+/// values captured by a parameterized route such as `/users/:id` and is
+/// only populated once the request has been dispatched through a
+/// [`Router`]. Header names in `headers` are stored lowercased so lookups
+/// via [`HttpRequest::header`] are case-insensitive.
+#[derive(Debug)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub path: String,
+    pub version: String,
+    pub params: HashMap<String, String>,
+    pub headers: HashMap<String, String>,
+    pub body: Option<Vec<u8>>,
+}
+
+impl HttpRequest {
+    /// Returns a captured path parameter, if the matched route declared one
+    /// with this name (e.g. `:id` is looked up as `"id"`).
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+
+    /// Looks up a header by name, case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(String::as_str)
+    }
+
+    /// Returns the request body, if one was present (governed by a
+    /// `Content-Length` header).
+    pub fn body(&self) -> Option<&[u8]> {
+        self.body.as_deref()
+    }
+
+    /// Returns the request body interpreted as UTF-8, if it is valid.
+    pub fn body_as_str(&self) -> Option<&str> {
+        self.body.as_deref().and_then(|b| std::str::from_utf8(b).ok())
+    }
+
+    /// Whether the connection this request arrived on should stay open for
+    /// another request, per the `Connection` header. Defaults to
+    /// keep-alive for HTTP/1.1 and close for everything else (HTTP/1.0's
+    /// own default).
+    pub fn keep_alive(&self) -> bool {
+        match self.header("connection") {
+            Some(value) => !value.eq_ignore_ascii_case("close"),
+            None => self.version == "HTTP/1.1",
+        }
+    }
+}
+
+/// Parses the first line of an HTTP request (e.g. `"GET /users/42 HTTP/1.1"`)
+/// into an [`HttpRequest`] with empty headers and no body. Returns `None` if
+/// the line doesn't have exactly three whitespace-separated parts.
+pub fn parse_request_line(request_line: &str) -> Option<HttpRequest> {
+    let parts: Vec<&str> = request_line.split_whitespace().collect();
+    if parts.len() != 3 {
+        return None;
+    }
+
+    Some(HttpRequest {
+        method: Method::parse(parts[0]),
+        path: parts[1].to_string(),
+        version: parts[2].to_string(),
+        params: HashMap::new(),
+        headers: HashMap::new(),
+        body: None,
+    })
+}
+
+/// The largest request body `read_request` will allocate a buffer for.
+/// Anything claiming a larger `Content-Length` is rejected before any
+/// allocation happens, rather than trusting the attacker-controlled value.
+pub const MAX_BODY_SIZE: usize = 10 * 1024 * 1024;
+
+/// Why [`read_request`] failed to produce a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadRequestError {
+    /// The stream was at EOF before any request line was read.
+    Eof,
+    /// The request line or a header line couldn't be parsed, or reading
+    /// from the stream failed (including a read timeout or a body cut
+    /// short of its `Content-Length`).
+    Malformed,
+    /// `Content-Length` exceeded [`MAX_BODY_SIZE`].
+    PayloadTooLarge,
+}
+
+/// Reads one full HTTP request (request line, headers, and body) from
+/// `reader`.
+///
+/// Headers are parsed into [`HttpRequest::headers`] with lowercased names.
+/// If a `Content-Length` header is present and no larger than
+/// [`MAX_BODY_SIZE`], exactly that many bytes are read from `reader` into
+/// [`HttpRequest::body`]. A `Content-Length` over the limit is rejected
+/// with [`ReadRequestError::PayloadTooLarge`] before any buffer is
+/// allocated.
+pub fn read_request<R: BufRead>(reader: &mut R) -> Result<HttpRequest, ReadRequestError> {
+    let mut request_line = String::new();
+    if reader
+        .read_line(&mut request_line)
+        .map_err(|_| ReadRequestError::Malformed)?
+        == 0
+    {
+        return Err(ReadRequestError::Eof);
+    }
+    let mut request =
+        parse_request_line(request_line.trim_end()).ok_or(ReadRequestError::Malformed)?;
+
+    loop {
+        let mut line = String::new();
+        let bytes_read = reader
+            .read_line(&mut line)
+            .map_err(|_| ReadRequestError::Malformed)?;
+        if bytes_read == 0 || line.trim().is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(':').ok_or(ReadRequestError::Malformed)?;
+        request
+            .headers
+            .insert(name.trim().to_lowercase(), value.trim().to_string());
+    }
+
+    let content_length = request
+        .header("content-length")
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(0);
+
+    if content_length > MAX_BODY_SIZE {
+        return Err(ReadRequestError::PayloadTooLarge);
+    }
+
+    if content_length > 0 {
+        let mut body = vec![0u8; content_length];
+        reader
+            .read_exact(&mut body)
+            .map_err(|_| ReadRequestError::Malformed)?;
+        request.body = Some(body);
+    }
+
+    Ok(request)
+}
+
+/// An HTTP response, built up with the `with_*` methods and serialized with
+/// [`HttpResponse::write_to`].
+pub struct HttpResponse {
+    pub status: u16,
+    pub reason: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn new(status: u16, reason: &str) -> HttpResponse {
+        HttpResponse {
+            status,
+            reason: reason.to_string(),
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn ok() -> HttpResponse {
+        HttpResponse::new(200, "OK")
+    }
+
+    pub fn not_found() -> HttpResponse {
+        HttpResponse::new(404, "NOT FOUND")
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> HttpResponse {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> HttpResponse {
+        self.body = body.into();
+        self
+    }
+
+    /// Convenience constructor for a plain-text response with the given
+    /// status, reason phrase, and UTF-8 body.
+    pub fn text(status: u16, reason: &str, body: impl Into<String>) -> HttpResponse {
+        HttpResponse::new(status, reason).with_body(body.into().into_bytes())
+    }
+
+    /// Writes the status line, headers (plus `Content-Length`), and body to
+    /// `writer` in HTTP/1.1 wire format.
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        write!(writer, "HTTP/1.1 {} {}\r\n", self.status, self.reason)?;
+        for (name, value) in &self.headers {
+            write!(writer, "{}: {}\r\n", name, value)?;
+        }
+        write!(writer, "Content-Length: {}\r\n\r\n", self.body.len())?;
+        writer.write_all(&self.body)
+    }
+}
+
+/// A handler is any thread-safe closure that turns a request into a
+/// response.
+pub type Handler = Arc<dyn Fn(&HttpRequest) -> HttpResponse + Send + Sync>;
+
+/// One segment of a parameterized route pattern, e.g. `/users/:id` becomes
+/// `[Literal("users"), Param("id")]`.
+enum Segment {
+    Literal(String),
+    Param(String),
+}
+
+fn split_segments(path: &str) -> Vec<&str> {
+    path.split('/').filter(|s| !s.is_empty()).collect()
+}
+
+/// Dynamic route registration for [`crate`]'s connection handling.
+///
+/// Literal routes (no `:param` segments) are matched via a `HashMap` lookup;
+/// parameterized routes fall back to segment-by-segment matching and are
+/// only consulted once the literal lookup misses.
+pub struct Router {
+    literal_routes: HashMap<(Method, String), Handler>,
+    param_routes: Vec<(Method, Vec<Segment>, Handler)>,
+    not_found_handler: Handler,
+}
+
+impl Router {
+    pub fn new() -> Router {
+        Router {
+            literal_routes: HashMap::new(),
+            param_routes: Vec::new(),
+            not_found_handler: Arc::new(|_req| HttpResponse::not_found()),
+        }
+    }
+
+    /// Registers `handler` for `method` and `path`. `path` may contain
+    /// `:name` segments, which are captured into [`HttpRequest::params`]
+    /// when the route matches.
+    pub fn route<F>(&mut self, method: Method, path: &str, handler: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        let handler: Handler = Arc::new(handler);
+        let segments = split_segments(path);
+        let has_params = segments.iter().any(|s| s.starts_with(':'));
+
+        if has_params {
+            let segments = segments
+                .into_iter()
+                .map(|s| match s.strip_prefix(':') {
+                    Some(name) => Segment::Param(name.to_string()),
+                    None => Segment::Literal(s.to_string()),
+                })
+                .collect();
+            self.param_routes.push((method, segments, handler));
+        } else {
+            self.literal_routes
+                .insert((method, path.to_string()), handler);
+        }
+    }
+
+    pub fn get<F>(&mut self, path: &str, handler: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.route(Method::Get, path, handler);
+    }
+
+    pub fn post<F>(&mut self, path: &str, handler: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.route(Method::Post, path, handler);
+    }
+
+    /// Overrides the handler invoked when no route matches. Defaults to a
+    /// bare 404.
+    pub fn set_not_found<F>(&mut self, handler: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.not_found_handler = Arc::new(handler);
+    }
+
+    fn match_params(segments: &[Segment], path: &str) -> Option<HashMap<String, String>> {
+        let path_segments = split_segments(path);
+        if path_segments.len() != segments.len() {
+            return None;
+        }
+
+        let mut params = HashMap::new();
+        for (segment, value) in segments.iter().zip(path_segments.iter()) {
+            match segment {
+                Segment::Literal(literal) => {
+                    if literal != value {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => {
+                    params.insert(name.clone(), value.to_string());
+                }
+            }
+        }
+        Some(params)
+    }
+
+    /// Looks up the handler for `request.method`/`request.path`, populating
+    /// `request.params` if a parameterized route matches, and invokes it.
+    /// Falls back to the not-found handler if nothing matches.
+    pub fn dispatch(&self, request: &mut HttpRequest) -> HttpResponse {
+        if let Some(handler) = self
+            .literal_routes
+            .get(&(request.method, request.path.clone()))
+        {
+            return handler(request);
+        }
+
+        for (method, segments, handler) in &self.param_routes {
+            if *method != request.method {
+                continue;
+            }
+            if let Some(params) = Self::match_params(segments, &request.path) {
+                request.params = params;
+                return handler(request);
+            }
+        }
+
+        (self.not_found_handler)(request)
+    }
+}
+
+impl Default for Router {
+    fn default() -> Router {
+        Router::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_route_matches() {
+        let mut router = Router::new();
+        router.get("/", |_req| HttpResponse::text(200, "OK", "home"));
+
+        let mut req = parse_request_line("GET / HTTP/1.1").unwrap();
+        let res = router.dispatch(&mut req);
+
+        assert_eq!(res.status, 200);
+        assert_eq!(res.body, b"home");
+    }
+
+    #[test]
+    fn param_route_captures_segment() {
+        let mut router = Router::new();
+        router.get("/users/:id", |req| {
+            HttpResponse::text(200, "OK", req.param("id").unwrap_or_default())
+        });
+
+        let mut req = parse_request_line("GET /users/42 HTTP/1.1").unwrap();
+        let res = router.dispatch(&mut req);
+
+        assert_eq!(res.body, b"42");
+    }
+
+    #[test]
+    fn unmatched_route_falls_back_to_not_found() {
+        let router = Router::new();
+        let mut req = parse_request_line("GET /missing HTTP/1.1").unwrap();
+        let res = router.dispatch(&mut req);
+
+        assert_eq!(res.status, 404);
+    }
+
+    #[test]
+    fn literal_route_is_preferred_over_param_route() {
+        let mut router = Router::new();
+        router.get("/users/:id", |_req| HttpResponse::text(200, "OK", "param"));
+        router.get("/users/me", |_req| HttpResponse::text(200, "OK", "literal"));
+
+        let mut req = parse_request_line("GET /users/me HTTP/1.1").unwrap();
+        let res = router.dispatch(&mut req);
+
+        assert_eq!(res.body, b"literal");
+    }
+
+    #[test]
+    fn read_request_parses_headers_and_body() {
+        let raw = "POST /submit HTTP/1.1\r\nContent-Type: text/plain\r\nContent-Length: 5\r\n\r\nhello";
+        let mut reader = io::Cursor::new(raw.as_bytes());
+
+        let req = read_request(&mut reader).unwrap();
+
+        assert_eq!(req.method, Method::Post);
+        assert_eq!(req.path, "/submit");
+        assert_eq!(req.header("content-type"), Some("text/plain"));
+        assert_eq!(req.header("Content-Type"), Some("text/plain"));
+        assert_eq!(req.body_as_str(), Some("hello"));
+    }
+
+    #[test]
+    fn read_request_without_body_leaves_body_none() {
+        let raw = "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        let mut reader = io::Cursor::new(raw.as_bytes());
+
+        let req = read_request(&mut reader).unwrap();
+
+        assert!(req.body().is_none());
+    }
+
+    #[test]
+    fn read_request_returns_eof_on_empty_stream() {
+        let mut reader = io::Cursor::new(b"" as &[u8]);
+        assert_eq!(read_request(&mut reader).unwrap_err(), ReadRequestError::Eof);
+    }
+
+    #[test]
+    fn read_request_rejects_oversized_content_length_without_allocating() {
+        let raw = format!(
+            "POST /submit HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_SIZE + 1
+        );
+        let mut reader = io::Cursor::new(raw.into_bytes());
+
+        assert_eq!(
+            read_request(&mut reader).unwrap_err(),
+            ReadRequestError::PayloadTooLarge
+        );
+    }
+
+    #[test]
+    fn keep_alive_defaults_by_version() {
+        let http11 = parse_request_line("GET / HTTP/1.1").unwrap();
+        assert!(http11.keep_alive());
+
+        let http10 = parse_request_line("GET / HTTP/1.0").unwrap();
+        assert!(!http10.keep_alive());
+    }
+
+    #[test]
+    fn keep_alive_honors_connection_header() {
+        let raw = "GET / HTTP/1.1\r\nConnection: close\r\n\r\n";
+        let mut reader = io::Cursor::new(raw.as_bytes());
+        let req = read_request(&mut reader).unwrap();
+        assert!(!req.keep_alive());
+
+        let raw = "GET / HTTP/1.0\r\nConnection: keep-alive\r\n\r\n";
+        let mut reader = io::Cursor::new(raw.as_bytes());
+        let req = read_request(&mut reader).unwrap();
+        assert!(req.keep_alive());
+    }
+}
@@ -3,7 +3,35 @@ use std::{
     thread,
 };
 
-type Job = Box<dyn FnOnce() + Send + 'static>;
+pub mod http;
+pub use http::{HttpRequest, HttpResponse, Method, Router};
+
+mod server;
+pub use server::{Server, ShutdownHandle};
+
+mod static_files;
+pub use static_files::StaticFiles;
+
+mod metrics;
+pub use metrics::Metrics;
+
+pub type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// What a [`ThreadPool`] does with a job submitted to [`ThreadPool::execute`]
+/// once its queue is already holding `max_queued` jobs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectionPolicy {
+    /// Block the calling thread until a slot frees up. This is the only
+    /// policy that guarantees `execute` never fails, and is what
+    /// `ThreadPool::new` uses.
+    Block,
+    /// Silently discard the incoming job, reporting success to the caller
+    /// as if it had been queued.
+    DropNewest,
+    /// Hand the job straight back to the caller as `Err`, so it can decide
+    /// how to respond (e.g. a `503 Service Unavailable`).
+    Reject,
+}
 
 /// Message type for communicating with worker threads.
 /// 
@@ -16,6 +44,16 @@ enum Message {
     Terminate,
 }
 
+/// The sending half of the job queue. `Unbounded` backs `ThreadPool::new`
+/// (truly unbounded, so sending never fails short of disconnection);
+/// `Bounded` backs `ThreadPool::with_capacity` and is the only variant
+/// `RejectionPolicy::DropNewest`/`Reject` apply to, since only a bounded
+/// channel can report "full".
+enum PoolSender {
+    Unbounded(mpsc::Sender<Message>),
+    Bounded(mpsc::SyncSender<Message>),
+}
+
 /// A thread pool for executing jobs concurrently.
 ///
 /// The ThreadPool manages a fixed number of worker threads that
@@ -35,11 +73,12 @@ enum Message {
 /// ```
 pub struct ThreadPool {
     workers: Vec<Worker>,
-    sender: Option<mpsc::Sender<Message>>,
+    sender: Option<PoolSender>,
+    policy: RejectionPolicy,
 }
 
 impl ThreadPool {
-    /// Creates a new ThreadPool.
+    /// Creates a new ThreadPool with an effectively unbounded queue.
     ///
     /// The `size` parameter specifies the number of worker threads
     /// in the pool. Each worker thread will process jobs from a
@@ -74,25 +113,62 @@ impl ThreadPool {
 
         ThreadPool {
             workers,
-            sender: Some(tx),
+            sender: Some(PoolSender::Unbounded(tx)),
+            policy: RejectionPolicy::Block,
+        }
+    }
+
+    /// Creates a new ThreadPool whose job queue holds at most `max_queued`
+    /// jobs at once, applying `policy` to jobs submitted once it's full.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The number of worker threads to create
+    /// * `max_queued` - The maximum number of jobs buffered ahead of the
+    ///   workers
+    /// * `policy` - What to do with a job submitted while the queue is full
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn with_capacity(size: usize, max_queued: usize, policy: RejectionPolicy) -> ThreadPool {
+        assert!(size > 0);
+
+        let (tx, rx) = mpsc::sync_channel(max_queued);
+        let rx = Arc::new(Mutex::new(rx));
+
+        let mut workers = Vec::with_capacity(size);
+
+        for id in 0..size {
+            workers.push(Worker::new(id, Arc::clone(&rx)))
+        }
+
+        ThreadPool {
+            workers,
+            sender: Some(PoolSender::Bounded(tx)),
+            policy,
         }
     }
 
-    /// Executes a closure on one of the worker threads.
+    /// Submits a closure to run on one of the worker threads.
     ///
-    /// The closure will be executed asynchronously by one of the
-    /// available worker threads. If all workers are busy, the job
-    /// will be queued until a worker becomes available.
+    /// With the default [`RejectionPolicy::Block`] this always succeeds,
+    /// blocking the caller if the queue is momentarily full. With
+    /// [`RejectionPolicy::Reject`], a full queue hands the job straight
+    /// back as `Err` instead of queueing it; with
+    /// [`RejectionPolicy::DropNewest`], a full queue silently discards it
+    /// and still reports `Ok`.
     ///
     /// # Arguments
     ///
     /// * `f` - A closure that takes no arguments and returns nothing.
-    ///          The closure must be `Send` and `'static`.
+    ///   The closure must be `Send` and `'static`.
     ///
     /// # Errors
     ///
-    /// This function will panic if the sender channel has been closed,
-    /// which typically only happens during shutdown.
+    /// Returns `Err` with the job that was rejected when the queue is full
+    /// and the pool's policy is `Reject` (or when the pool has already shut
+    /// down).
     ///
     /// # Example
     ///
@@ -100,21 +176,44 @@ impl ThreadPool {
     /// use webserver::ThreadPool;
     ///
     /// let pool = ThreadPool::new(4);
-    /// pool.execute(|| {
+    /// let _ = pool.execute(|| {
     ///     println!("This runs on a worker thread");
     /// });
     /// ```
-    pub fn execute<F>(&self, f: F)
+    pub fn execute<F>(&self, f: F) -> Result<(), Job>
     where
         F: FnOnce() + Send + 'static,
     {
-        let job = Box::new(f);
-        let message = Message::NewJob(job);
-        
-        if let Some(sender) = self.sender.as_ref() {
-            sender
-                .send(message)
-                .expect("Should've been able to send job to worker");
+        let job: Job = Box::new(f);
+
+        match self.sender.as_ref() {
+            Some(PoolSender::Unbounded(sender)) => {
+                sender
+                    .send(Message::NewJob(job))
+                    .expect("Should've been able to send job to worker");
+                Ok(())
+            }
+            Some(PoolSender::Bounded(sender)) => match self.policy {
+                RejectionPolicy::Block => {
+                    sender
+                        .send(Message::NewJob(job))
+                        .expect("Should've been able to send job to worker");
+                    Ok(())
+                }
+                RejectionPolicy::DropNewest => match sender.try_send(Message::NewJob(job)) {
+                    Ok(()) | Err(mpsc::TrySendError::Full(_)) => Ok(()),
+                    Err(mpsc::TrySendError::Disconnected(Message::NewJob(job))) => Err(job),
+                    Err(mpsc::TrySendError::Disconnected(Message::Terminate)) => unreachable!(),
+                },
+                RejectionPolicy::Reject => match sender.try_send(Message::NewJob(job)) {
+                    Ok(()) => Ok(()),
+                    Err(mpsc::TrySendError::Full(Message::NewJob(job))) => Err(job),
+                    Err(mpsc::TrySendError::Disconnected(Message::NewJob(job))) => Err(job),
+                    Err(mpsc::TrySendError::Full(Message::Terminate))
+                    | Err(mpsc::TrySendError::Disconnected(Message::Terminate)) => unreachable!(),
+                },
+            },
+            None => Err(job),
         }
     }
 }
@@ -124,7 +223,10 @@ impl Drop for ThreadPool {
         // Send explicit terminate messages to all workers
         if let Some(sender) = self.sender.take() {
             for _ in &self.workers {
-                let _ = sender.send(Message::Terminate);
+                let _ = match &sender {
+                    PoolSender::Unbounded(sender) => sender.send(Message::Terminate),
+                    PoolSender::Bounded(sender) => sender.send(Message::Terminate),
+                };
             }
         }
 
@@ -185,16 +287,22 @@ mod tests {
     use std::sync::{Arc, Mutex};
     use std::time::Duration;
 
+    /// `Job` doesn't implement `Debug`, so `Result<(), Job>::unwrap` isn't
+    /// available; this asserts success without needing it.
+    fn assert_queued(result: Result<(), Job>) {
+        assert!(result.is_ok(), "job was rejected");
+    }
+
     #[test]
     fn test_thread_pool_executes_job() {
         let pool = ThreadPool::new(2);
         let counter = Arc::new(Mutex::new(0));
 
         let counter_clone = Arc::clone(&counter);
-        pool.execute(move || {
+        assert_queued(pool.execute(move || {
             let mut count = counter_clone.lock().unwrap();
             *count += 1;
-        });
+        }));
 
         // Give the worker thread time to execute
         thread::sleep(Duration::from_millis(100));
@@ -210,10 +318,10 @@ mod tests {
 
         for _ in 0..5 {
             let counter_clone = Arc::clone(&counter);
-            pool.execute(move || {
+            assert_queued(pool.execute(move || {
                 let mut count = counter_clone.lock().unwrap();
                 *count += 1;
-            });
+            }));
         }
 
         // Give worker threads time to execute all jobs
@@ -229,10 +337,10 @@ mod tests {
         let counter = Arc::new(Mutex::new(0));
 
         let counter_clone = Arc::clone(&counter);
-        pool.execute(move || {
+        assert_queued(pool.execute(move || {
             let mut count = counter_clone.lock().unwrap();
             *count += 1;
-        });
+        }));
 
         // Drop the pool, which should trigger graceful shutdown
         drop(pool);
@@ -241,4 +349,46 @@ mod tests {
         let count = counter.lock().unwrap();
         assert_eq!(*count, 1);
     }
+
+    #[test]
+    fn test_reject_policy_rejects_once_queue_is_full() {
+        // One worker, one buffered slot: the first job occupies the worker
+        // and the second fills the buffer, so a third has nowhere to go.
+        let pool = ThreadPool::with_capacity(1, 1, RejectionPolicy::Reject);
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+
+        assert_queued(pool.execute(move || {
+            let _ = release_rx.recv();
+        }));
+
+        // Give the worker time to pick up the blocking job so the buffer
+        // slot below is genuinely free, not racing the worker for it.
+        thread::sleep(Duration::from_millis(50));
+
+        assert_queued(pool.execute(|| {}));
+
+        let result = pool.execute(|| {});
+        assert!(result.is_err());
+
+        let _ = release_tx.send(());
+    }
+
+    #[test]
+    fn test_drop_newest_policy_reports_ok_for_discarded_job() {
+        let pool = ThreadPool::with_capacity(1, 1, RejectionPolicy::DropNewest);
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+
+        assert_queued(pool.execute(move || {
+            let _ = release_rx.recv();
+        }));
+
+        thread::sleep(Duration::from_millis(50));
+
+        assert_queued(pool.execute(|| {}));
+
+        let result = pool.execute(|| {});
+        assert!(result.is_ok());
+
+        let _ = release_tx.send(());
+    }
 }
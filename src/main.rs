@@ -1,19 +1,69 @@
 use std::{
-    fs,
     io::{BufReader, prelude::*},
-    net::{TcpListener, TcpStream},
+    net::TcpStream,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use webserver::ThreadPool;
+use webserver::http::ReadRequestError;
+use webserver::{Metrics, RejectionPolicy, Router, Server, StaticFiles};
+
+/// Maximum number of requests served on one keep-alive connection before it
+/// is closed regardless of what the client asked for.
+const MAX_REQUESTS_PER_CONNECTION: u32 = 100;
+
+/// How long a keep-alive connection may sit idle waiting for the next
+/// request before it's dropped.
+///
+/// This server is thread-per-connection: a kept-alive connection occupies
+/// one worker thread for this entire window even while no request is in
+/// flight. Keep it short, since every second here is a second another
+/// client's request can be stuck behind a full pool instead of getting a
+/// prompt `503`. See `WORKER_POOL_SIZE` for the other half of this trade-off.
+const IDLE_READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Number of worker threads in the pool. Because an idle keep-alive
+/// connection ties up a worker for up to `IDLE_READ_TIMEOUT`, this needs to
+/// comfortably exceed the number of concurrent persistent connections you
+/// expect, not just the number of requests actually in flight at once — a
+/// single browser alone typically opens ~6 keep-alive connections.
+const WORKER_POOL_SIZE: usize = 32;
+
+/// Maximum number of accepted connections buffered ahead of the worker pool.
+const MAX_QUEUED_CONNECTIONS: usize = 100;
+
+/// The `SIGINT` constant from `signal.h`.
+const SIGINT: i32 = 2;
+
+/// Set by `handle_sigint`; a background thread polls this and turns it into
+/// a call to `ShutdownHandle::shutdown`. A real signal handler can't safely
+/// do much more than an atomic store, so the rest of the shutdown logic
+/// lives outside of it.
+static SIGINT_RECEIVED: AtomicBool = AtomicBool::new(false);
+
+extern "C" {
+    fn signal(signum: i32, handler: usize) -> usize;
+}
+
+extern "C" fn handle_sigint(_signum: i32) {
+    SIGINT_RECEIVED.store(true, Ordering::SeqCst);
+}
 
 fn main() {
     let address = "127.0.0.1:7878";
-    let listener = match TcpListener::bind(address) {
-        Ok(listener) => {
+    let server = match Server::bind_with_capacity(
+        address,
+        WORKER_POOL_SIZE,
+        MAX_QUEUED_CONNECTIONS,
+        RejectionPolicy::Reject,
+    ) {
+        Ok(server) => {
             println!("Server listening on {}", address);
-            listener
+            server
         }
         Err(e) => {
             eprintln!("Failed to bind to {}: {}", address, e);
@@ -21,94 +71,72 @@ fn main() {
         }
     };
 
-    let pool = ThreadPool::new(4);
+    // SAFETY: `handle_sigint` only performs an atomic store, which is safe
+    // to run from a signal handler.
+    unsafe {
+        signal(SIGINT, handle_sigint as *const () as usize);
+    }
 
-    for stream in listener.incoming() {
-        let stream = match stream {
-            Ok(stream) => stream,
-            Err(e) => {
-                eprintln!("Error accepting connection: {}", e);
-                continue;
+    // A background thread turns the flag the signal handler sets into an
+    // actual call to `ShutdownHandle::shutdown`, since that's not something
+    // safe to do directly from the handler itself.
+    let shutdown = server.shutdown_handle();
+    thread::spawn(move || {
+        loop {
+            if SIGINT_RECEIVED.load(Ordering::SeqCst) {
+                println!("Received Ctrl-C, requesting shutdown.");
+                shutdown.shutdown();
+                return;
             }
-        };
+            thread::sleep(Duration::from_millis(100));
+        }
+    });
+
+    let static_files = Arc::new(StaticFiles::new("."));
+    let metrics = Arc::new(Metrics::new());
+
+    let mut router = Router::new();
+    router.get("/metrics", Metrics::handler(Arc::clone(&metrics)));
+    router.get("/", {
+        let static_files = Arc::clone(&static_files);
+        move |_req| static_files.serve("/index.html")
+    });
+    router.get("/sleep", {
+        let static_files = Arc::clone(&static_files);
+        move |_req| {
+            thread::sleep(Duration::from_secs(5));
+            static_files.serve("/index.html")
+        }
+    });
+    router.set_not_found({
+        let static_files = Arc::clone(&static_files);
+        move |req| {
+            let response = static_files.serve(&req.path);
+            if response.status == 404 {
+                static_files.serve_with_status("/404.html", 404, "NOT FOUND")
+            } else {
+                response
+            }
+        }
+    });
+    let router = Arc::new(router);
 
-        pool.execute(|| {
-            handle_connection(stream);
-        });
-    }
+    server.run(move |stream| {
+        handle_connection(stream, &router, &metrics);
+    });
 
     println!("Shutting server down.")
 }
 
-/// Represents a parsed HTTP request line.
-struct HttpRequest {
-    method: String,
-    path: String,
-    #[allow(dead_code)]
-    version: String, // Parsed but not currently used in routing logic
-}
-
-/// Parses the HTTP request line into method, path, and version.
-///
-/// # Arguments
-///
-/// * `request_line` - The first line of the HTTP request (e.g., "GET / HTTP/1.1")
-///
-/// # Returns
-///
-/// Returns `Some(HttpRequest)` if parsing succeeds, `None` otherwise.
-fn parse_request_line(request_line: &str) -> Option<HttpRequest> {
-    let parts: Vec<&str> = request_line.split_whitespace().collect();
-    if parts.len() != 3 {
-        return None;
-    }
-
-    Some(HttpRequest {
-        method: parts[0].to_string(),
-        path: parts[1].to_string(),
-        version: parts[2].to_string(),
-    })
-}
-
-/// Reads HTTP headers from the request stream.
-///
-/// Headers are read line by line until an empty line is encountered,
-/// which marks the end of the headers section.
-///
-/// # Arguments
-///
-/// * `lines` - An iterator over the lines of the request
-///
-/// # Returns
-///
-/// Returns a vector of header lines (excluding the empty line).
-fn read_headers<I>(lines: &mut I) -> Vec<String>
-where
-    I: Iterator<Item = Result<String, std::io::Error>>,
-{
-    let mut headers = Vec::new();
-    for line_result in lines {
-        match line_result {
-            Ok(line) => {
-                // Empty line indicates end of headers
-                if line.trim().is_empty() {
-                    break;
-                }
-                headers.push(line);
-            }
-            Err(_) => break,
-        }
-    }
-    headers
-}
-
-/// Handles an incoming TCP connection, parsing the HTTP request and sending a response.
+/// Handles an incoming TCP connection, serving requests on it until the
+/// client asks to close, the connection goes idle, or the per-connection
+/// request limit is hit.
 ///
-/// This function:
-/// 1. Parses the HTTP request line to extract method and path
-/// 2. Reads HTTP headers (if present)
-/// 3. Routes the request based on method and path
-/// 4. Sends an appropriate HTTP response
+/// For each request, this:
+/// 1. Reads the request line, headers, and (if present) body
+/// 2. Dispatches the request through `router`
+/// 3. Sends the resulting response, with a `Connection` header reflecting
+///    whether the loop will read another request from this stream
 ///
 /// All errors are handled gracefully, sending appropriate HTTP error responses
 /// to the client rather than panicking.
@@ -116,68 +144,66 @@ where
 /// # Arguments
 ///
 /// * `stream` - The TCP stream representing the client connection
-fn handle_connection(mut stream: TcpStream) {
-    let buf_reader = BufReader::new(&stream);
-    let mut lines = buf_reader.lines();
-
-    // Parse the request line
-    let request_line = match lines.next() {
-        Some(Ok(line)) => line,
-        Some(Err(e)) => {
-            eprintln!("Error reading request line: {}", e);
-            send_error_response(&mut stream, "400 Bad Request", "Invalid request");
-            return;
-        }
-        None => {
-            eprintln!("Empty request received");
-            send_error_response(&mut stream, "400 Bad Request", "Empty request");
-            return;
-        }
-    };
+/// * `router` - The route table used to pick a handler for this request
+/// * `metrics` - Shared counters updated for every connection and response
+fn handle_connection(stream: TcpStream, router: &Router, metrics: &Metrics) {
+    metrics.record_connection();
 
-    // Parse method, path, and version
-    let http_request = match parse_request_line(&request_line) {
-        Some(req) => req,
-        None => {
-            eprintln!("Failed to parse request line: {}", request_line);
-            send_error_response(&mut stream, "400 Bad Request", "Malformed request line");
+    if let Err(e) = stream.set_read_timeout(Some(IDLE_READ_TIMEOUT)) {
+        eprintln!("Error setting read timeout: {}", e);
+    }
+
+    // The `BufReader` owns a cloned handle of the stream for the whole
+    // loop; responses are written back through that same handle via
+    // `buf_reader.get_mut()`, which bypasses the read buffer.
+    let read_stream = match stream.try_clone() {
+        Ok(read_stream) => read_stream,
+        Err(e) => {
+            eprintln!("Error cloning stream for reading: {}", e);
             return;
         }
     };
+    let mut buf_reader = BufReader::new(read_stream);
+    let mut requests_served = 0u32;
+
+    loop {
+        let mut http_request = match webserver::http::read_request(&mut buf_reader) {
+            Ok(req) => req,
+            Err(ReadRequestError::Eof) => return,
+            Err(ReadRequestError::Malformed) => {
+                eprintln!("Failed to parse request");
+                send_error_response(buf_reader.get_mut(), "400 Bad Request", "Malformed request");
+                return;
+            }
+            Err(ReadRequestError::PayloadTooLarge) => {
+                eprintln!("Rejected request with oversized Content-Length");
+                send_error_response(
+                    buf_reader.get_mut(),
+                    "413 Payload Too Large",
+                    "Request body too large",
+                );
+                return;
+            }
+        };
+        requests_served += 1;
 
-    // Read headers (we don't use them yet, but we parse them to demonstrate understanding)
-    let _headers = read_headers(&mut lines);
+        let keep_alive = http_request.keep_alive() && requests_served < MAX_REQUESTS_PER_CONNECTION;
 
-    // Route based on method and path
-    let (status_line, filename) = match (http_request.method.as_str(), http_request.path.as_str()) {
-        ("GET", "/") => ("HTTP/1.1 200 OK", "index.html"),
-        ("GET", "/sleep") => {
-            thread::sleep(Duration::from_secs(5));
-            ("HTTP/1.1 200 OK", "index.html")
-        }
-        _ => ("HTTP/1.1 404 NOT FOUND", "404.html"),
-    };
+        let started_at = Instant::now();
+        let response = router
+            .dispatch(&mut http_request)
+            .with_header("Connection", if keep_alive { "keep-alive" } else { "close" });
+        metrics.record_duration(started_at.elapsed());
+        metrics.record_response(response.status);
 
-    // Read the file content
-    let content = match fs::read_to_string(filename) {
-        Ok(content) => content,
-        Err(e) => {
-            eprintln!("Error reading file {}: {}", filename, e);
-            send_error_response(&mut stream, "500 Internal Server Error", "Failed to read file");
+        if let Err(e) = response.write_to(buf_reader.get_mut()) {
+            eprintln!("Error sending response: {}", e);
             return;
         }
-    };
-
-    // Send the response
-    let response = format!(
-        "{}\r\nContent-Length: {}\r\n\r\n{}",
-        status_line,
-        content.len(),
-        content
-    );
 
-    if let Err(e) = stream.write_all(response.as_bytes()) {
-        eprintln!("Error sending response: {}", e);
+        if !keep_alive {
+            return;
+        }
     }
 }
 
@@ -0,0 +1,148 @@
+//! Built-in request metrics, rendered as a plain-text snapshot for
+//! scraping.
+//!
+//! Every counter is a standalone atomic rather than fields behind one
+//! `Mutex`, so incrementing metrics on every request doesn't serialize
+//! worker threads against each other on the hot path.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::http::{HttpRequest, HttpResponse};
+
+const DURATION_BUCKET_LABELS: [&str; 5] = [
+    "handler_duration_lt_1ms",
+    "handler_duration_lt_10ms",
+    "handler_duration_lt_100ms",
+    "handler_duration_lt_1s",
+    "handler_duration_gte_1s",
+];
+
+fn duration_bucket(duration: Duration) -> usize {
+    match duration.as_millis() {
+        0 => 0,
+        1..=9 => 1,
+        10..=99 => 2,
+        100..=999 => 3,
+        _ => 4,
+    }
+}
+
+/// Tracks total connections accepted, response counts grouped by status
+/// class, and a histogram of handler durations.
+pub struct Metrics {
+    total_connections: AtomicU64,
+    status_2xx: AtomicU64,
+    status_3xx: AtomicU64,
+    status_4xx: AtomicU64,
+    status_5xx: AtomicU64,
+    status_other: AtomicU64,
+    duration_buckets: [AtomicU64; 5],
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics {
+            total_connections: AtomicU64::new(0),
+            status_2xx: AtomicU64::new(0),
+            status_3xx: AtomicU64::new(0),
+            status_4xx: AtomicU64::new(0),
+            status_5xx: AtomicU64::new(0),
+            status_other: AtomicU64::new(0),
+            duration_buckets: [
+                AtomicU64::new(0),
+                AtomicU64::new(0),
+                AtomicU64::new(0),
+                AtomicU64::new(0),
+                AtomicU64::new(0),
+            ],
+        }
+    }
+
+    /// Records that a connection was accepted.
+    pub fn record_connection(&self) {
+        self.total_connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a response's status code against its status class (2xx..5xx).
+    pub fn record_response(&self, status: u16) {
+        let counter = match status {
+            200..=299 => &self.status_2xx,
+            300..=399 => &self.status_3xx,
+            400..=499 => &self.status_4xx,
+            500..=599 => &self.status_5xx,
+            _ => &self.status_other,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records how long a handler took to produce a response.
+    pub fn record_duration(&self, duration: Duration) {
+        self.duration_buckets[duration_bucket(duration)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Renders all counters as `name value` lines, one per line, so an
+    /// operator can scrape the running server over HTTP.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&format!(
+            "total_connections {}\n",
+            self.total_connections.load(Ordering::Relaxed)
+        ));
+        out.push_str(&format!("status_2xx {}\n", self.status_2xx.load(Ordering::Relaxed)));
+        out.push_str(&format!("status_3xx {}\n", self.status_3xx.load(Ordering::Relaxed)));
+        out.push_str(&format!("status_4xx {}\n", self.status_4xx.load(Ordering::Relaxed)));
+        out.push_str(&format!("status_5xx {}\n", self.status_5xx.load(Ordering::Relaxed)));
+        out.push_str(&format!(
+            "status_other {}\n",
+            self.status_other.load(Ordering::Relaxed)
+        ));
+        for (label, bucket) in DURATION_BUCKET_LABELS.iter().zip(&self.duration_buckets) {
+            out.push_str(&format!("{} {}\n", label, bucket.load(Ordering::Relaxed)));
+        }
+        out
+    }
+
+    /// Builds a router handler that renders this `Metrics`' snapshot as a
+    /// plain-text response, e.g. for registering at `/metrics`.
+    pub fn handler(metrics: Arc<Metrics>) -> impl Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static {
+        move |_req| HttpResponse::text(200, "OK", metrics.render())
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Metrics {
+        Metrics::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_connections_and_statuses() {
+        let metrics = Metrics::new();
+        metrics.record_connection();
+        metrics.record_connection();
+        metrics.record_response(200);
+        metrics.record_response(404);
+
+        let snapshot = metrics.render();
+        assert!(snapshot.contains("total_connections 2"));
+        assert!(snapshot.contains("status_2xx 1"));
+        assert!(snapshot.contains("status_4xx 1"));
+    }
+
+    #[test]
+    fn buckets_durations_by_magnitude() {
+        let metrics = Metrics::new();
+        metrics.record_duration(Duration::from_millis(0));
+        metrics.record_duration(Duration::from_millis(500));
+
+        let snapshot = metrics.render();
+        assert!(snapshot.contains("handler_duration_lt_1ms 1"));
+        assert!(snapshot.contains("handler_duration_lt_1s 1"));
+    }
+}
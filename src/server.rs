@@ -0,0 +1,159 @@
+//! A `TcpListener` + `ThreadPool` pairing with a real shutdown path, so a
+//! caller doesn't have to rely on the process exiting to stop the server.
+
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::http::HttpResponse;
+use crate::{RejectionPolicy, ThreadPool};
+
+/// How often the accept loop wakes up to check the shutdown flag while no
+/// connection is pending.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A cloneable token that requests shutdown of the `Server` it came from.
+///
+/// Clone it into a Ctrl-C handler (or anywhere else) and call
+/// [`ShutdownHandle::shutdown`] to stop the accept loop.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    flag: Arc<AtomicBool>,
+}
+
+impl ShutdownHandle {
+    /// Requests shutdown. The accept loop notices on its next poll.
+    pub fn shutdown(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+    }
+}
+
+/// Wraps a `TcpListener` and a [`ThreadPool`], accepting connections until
+/// asked to stop.
+///
+/// The listener is nonblocking so the accept loop can poll the shutdown
+/// flag between connections instead of blocking on `accept()` forever.
+pub struct Server {
+    listener: TcpListener,
+    pool: ThreadPool,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl Server {
+    /// Binds `addr` and creates a worker pool of `pool_size` threads with an
+    /// effectively unbounded queue.
+    pub fn bind<A: ToSocketAddrs>(addr: A, pool_size: usize) -> std::io::Result<Server> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+
+        Ok(Server {
+            listener,
+            pool: ThreadPool::new(pool_size),
+            shutdown: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Binds `addr` and creates a worker pool of `pool_size` threads whose
+    /// job queue holds at most `max_queued` jobs, applying `policy` once
+    /// it's full. Use this to give the server real backpressure instead of
+    /// queueing connections without limit.
+    pub fn bind_with_capacity<A: ToSocketAddrs>(
+        addr: A,
+        pool_size: usize,
+        max_queued: usize,
+        policy: RejectionPolicy,
+    ) -> std::io::Result<Server> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+
+        Ok(Server {
+            listener,
+            pool: ThreadPool::with_capacity(pool_size, max_queued, policy),
+            shutdown: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Returns a cloneable handle that can request shutdown of this server.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle {
+            flag: Arc::clone(&self.shutdown),
+        }
+    }
+
+    /// Accepts connections and dispatches each to `handle` on the thread
+    /// pool until shutdown is requested, then drains the queue and joins
+    /// every worker before returning.
+    pub fn run<F>(self, handle: F)
+    where
+        F: Fn(TcpStream) + Send + Sync + 'static,
+    {
+        let handle = Arc::new(handle);
+
+        while !self.shutdown.load(Ordering::SeqCst) {
+            match self.listener.accept() {
+                Ok((stream, _addr)) => {
+                    let handle = Arc::clone(&handle);
+                    // Cloned up front so we can still respond if the pool
+                    // rejects the job outright (the original `stream` is
+                    // moved into the job itself).
+                    let rejection_stream = stream.try_clone();
+                    if let Err(_job) = self.pool.execute(move || handle(stream)) {
+                        respond_service_unavailable(rejection_stream);
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(POLL_INTERVAL);
+                }
+                Err(e) => {
+                    // A persistent non-blocking error (e.g. EMFILE from an
+                    // fd leak) would otherwise spin this loop at full CPU,
+                    // logging forever; back off the same as `WouldBlock`.
+                    eprintln!("Error accepting connection: {}", e);
+                    thread::sleep(POLL_INTERVAL);
+                }
+            }
+        }
+
+        println!("Shutdown requested, draining job queue and joining workers.");
+        // Dropping `self.pool` here sends `Message::Terminate` to every
+        // worker and joins them; see `ThreadPool`'s `Drop` impl.
+    }
+}
+
+/// Sends a `503 Service Unavailable` on `stream`, used when the thread
+/// pool's queue is full and rejects a connection.
+fn respond_service_unavailable(stream: std::io::Result<TcpStream>) {
+    match stream {
+        Ok(mut stream) => {
+            let response = HttpResponse::text(503, "Service Unavailable", "Server is overloaded");
+            if let Err(e) = response.write_to(&mut stream) {
+                eprintln!("Error sending 503 response: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Could not clone stream to send 503 response: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shutdown_handle_reports_requested_shutdown() {
+        let flag = Arc::new(AtomicBool::new(false));
+        let handle = ShutdownHandle {
+            flag: Arc::clone(&flag),
+        };
+
+        assert!(!handle.is_shutdown());
+        handle.shutdown();
+        assert!(handle.is_shutdown());
+    }
+}
@@ -0,0 +1,150 @@
+//! Serves files from a directory root as HTTP responses, with MIME-type
+//! inference and protection against paths that escape the root.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::http::HttpResponse;
+
+/// Serves files under `root` as HTTP responses.
+///
+/// Request paths are resolved to a filesystem path under `root` and
+/// canonicalized; anything that canonicalizes outside of `root` (e.g. via
+/// `../`) is treated as not found rather than served.
+pub struct StaticFiles {
+    root: PathBuf,
+}
+
+impl StaticFiles {
+    pub fn new(root: impl Into<PathBuf>) -> StaticFiles {
+        StaticFiles { root: root.into() }
+    }
+
+    /// Serves `request_path` (e.g. `/css/site.css`) from `root`. A trailing
+    /// `/` (or an empty path) resolves to `index.html`. Returns a 404
+    /// response if the file is missing or the path escapes `root`.
+    pub fn serve(&self, request_path: &str) -> HttpResponse {
+        self.serve_with_status(request_path, 200, "OK")
+    }
+
+    /// Like [`StaticFiles::serve`], but the successful response carries
+    /// `status`/`reason` instead of `200 OK`. Useful for serving a file
+    /// (e.g. `404.html`) as the body of an error response without the file
+    /// read itself being mistaken for success.
+    pub fn serve_with_status(&self, request_path: &str, status: u16, reason: &str) -> HttpResponse {
+        match self.resolve(request_path) {
+            Some(path) => match fs::read(&path) {
+                Ok(bytes) => HttpResponse::new(status, reason)
+                    .with_header("Content-Type", mime_type(&path))
+                    .with_body(bytes),
+                Err(_) => HttpResponse::not_found(),
+            },
+            None => HttpResponse::not_found(),
+        }
+    }
+
+    /// Resolves `request_path` to an absolute path under `root`, returning
+    /// `None` if the target doesn't exist or canonicalizes outside `root`.
+    fn resolve(&self, request_path: &str) -> Option<PathBuf> {
+        let relative = request_path.trim_start_matches('/');
+        let relative = if relative.is_empty() || relative.ends_with('/') {
+            format!("{relative}index.html")
+        } else {
+            relative.to_string()
+        };
+
+        let root = self.root.canonicalize().ok()?;
+        let candidate = root.join(&relative).canonicalize().ok()?;
+
+        if candidate.starts_with(&root) {
+            Some(candidate)
+        } else {
+            None
+        }
+    }
+}
+
+/// Infers a `Content-Type` from a file extension, falling back to a generic
+/// binary type for anything unrecognized.
+fn mime_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("html") | Some("htm") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") => "application/javascript",
+        Some("json") => "application/json",
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        Some("ico") => "image/x-icon",
+        Some("wasm") => "application/wasm",
+        Some("txt") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("webserver-static-files-test-{name}"));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn serves_existing_file_with_inferred_mime_type() {
+        let root = temp_root("serve");
+        let mut file = File::create(root.join("style.css")).unwrap();
+        file.write_all(b"body { color: red; }").unwrap();
+
+        let static_files = StaticFiles::new(&root);
+        let res = static_files.serve("/style.css");
+
+        assert_eq!(res.status, 200);
+        assert_eq!(res.body, b"body { color: red; }");
+        assert!(res
+            .headers
+            .iter()
+            .any(|(name, value)| name == "Content-Type" && value.starts_with("text/css")));
+    }
+
+    #[test]
+    fn rejects_path_traversal_outside_root() {
+        let root = temp_root("traversal");
+        fs::create_dir_all(root.join("public")).unwrap();
+        let secret = root.join("secret.txt");
+        File::create(&secret).unwrap().write_all(b"nope").unwrap();
+
+        let static_files = StaticFiles::new(root.join("public"));
+        let res = static_files.serve("/../secret.txt");
+
+        assert_eq!(res.status, 404);
+    }
+
+    #[test]
+    fn missing_file_is_404() {
+        let root = temp_root("missing");
+        let static_files = StaticFiles::new(&root);
+        let res = static_files.serve("/nope.html");
+
+        assert_eq!(res.status, 404);
+    }
+
+    #[test]
+    fn serve_with_status_overrides_success_status() {
+        let root = temp_root("serve-with-status");
+        let mut file = File::create(root.join("404.html")).unwrap();
+        file.write_all(b"<h1>not found</h1>").unwrap();
+
+        let static_files = StaticFiles::new(&root);
+        let res = static_files.serve_with_status("/404.html", 404, "NOT FOUND");
+
+        assert_eq!(res.status, 404);
+        assert_eq!(res.body, b"<h1>not found</h1>");
+    }
+}